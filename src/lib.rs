@@ -1,6 +1,12 @@
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+use std::collections::HashMap;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::fs;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::io::SeekFrom;
+use std::time::UNIX_EPOCH;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
 use tokio::net::{UnixListener, UnixStream};
 
 /// Creates a Unix domain socket at the given path.
@@ -24,49 +30,63 @@ pub fn create_socket(socket_path: String) -> std::io::Result<UnixListener> {
     Ok(listener)
 }
 
-// Parses a raw HTTP request string and extracts the target file path.
+/// A fully parsed HTTP request: method, path, version, headers, and body.
 ///
-/// This function supports only `GET` requests with HTTP/1.0 or HTTP/1.1. It trims the leading `/`
-/// from the path and prepends `"static/"` to resolve the file path. If the path is empty, it defaults
-/// to `"static/index.html"`.
+/// Built by `read_socket`, which loops on the socket until the complete header block and any
+/// declared `Content-Length` body have been read, so requests are no longer truncated by a
+/// fixed-size read.
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub version: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Looks up a header value by name, case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Resolves the target file path for a parsed `Request`.
+///
+/// This function supports `GET` and `HEAD` requests. It trims the leading `/` from the path
+/// and prepends `"static/"` to resolve the file path. If the path is empty, it defaults to
+/// `"static/index.html"`.
 ///
 /// # Arguments
 ///
-/// * `request` - A string slice representing the raw HTTP request.
+/// * `request` - The parsed HTTP request.
 ///
 /// # Returns
 ///
 /// Returns `Some(String)` with the resolved file path if the request is valid and supported, or `None` otherwise.
-pub fn parse_request(request: &str) -> Option<String> {
-    let mut lines = request.lines();
-    if let Some(first_line) = lines.next() {
-        let mut parts = first_line.split_whitespace();
-        let method = parts.next().unwrap_or("");
-        let path = parts.next().unwrap_or("/");
-        let version = parts.next().unwrap_or("");
-
-        if version != "HTTP/1.1" && version != "HTTP/1.0" {
-            eprintln!("Unsupported HTTP version: {}", version);
-            return None;
-        }
+pub fn parse_request(request: &Request) -> Option<String> {
+    if request.method != "GET" && request.method != "HEAD" {
+        eprintln!("Unsupported HTTP method: {}", request.method);
+        return None;
+    }
 
-        if method == "GET" {
-            let path = path.trim_start_matches('/');
+    if request.version != "HTTP/1.1" && request.version != "HTTP/1.0" {
+        eprintln!("Unsupported HTTP version: {}", request.version);
+        return None;
+    }
 
-            // Reject paths that try to traverse outside the static directory
-            if path.split('/').any(|part| part == "..") {
-                eprintln!("Path traversal attempt: {}", path);
-                return None;
-            }
+    let path = request.path.trim_start_matches('/');
 
-            return resolve_static_path(path);
-        } else {
-            eprintln!("Unsupported HTTP method: {}", method);
-            return None;
-        }
+    // Reject paths that try to traverse outside the static directory
+    if path.split('/').any(|part| part == "..") {
+        eprintln!("Path traversal attempt: {}", path);
+        return None;
     }
 
-    None
+    resolve_static_path(path)
 }
 
 /// Resolves a user-facing URL path to a static file path on disk using fallback rules.
@@ -80,7 +100,9 @@ pub fn parse_request(request: &str) -> Option<String> {
 ///     - `static/about/index.html`
 /// - A path with an extension (e.g., `/style.css`) is used as-is.
 ///
-/// The first path that exists and is a regular file is returned.
+/// The first path that exists and is a regular file is returned. If none of the candidate
+/// files exist but the directory itself does, the bare directory path is returned instead so
+/// callers can serve an autoindex listing for it.
 ///
 /// # Arguments
 ///
@@ -88,22 +110,28 @@ pub fn parse_request(request: &str) -> Option<String> {
 ///
 /// # Returns
 ///
-/// * `Some(String)` if a valid file is found under the `static/` directory.
-/// * `None` if no matching file exists.
+/// * `Some(String)` if a matching file, or otherwise an existing directory, is found under the
+///   `static/` directory.
+/// * `None` if neither exists.
 fn resolve_static_path(path: &str) -> Option<String> {
     let static_dir = PathBuf::from("static");
 
-    let candidates = if path.is_empty() {
-        vec![static_dir.join("index.html")]
+    let (candidates, dir_fallback) = if path.is_empty() {
+        (vec![static_dir.join("index.html")], Some(static_dir.clone()))
     } else if path.ends_with('/') {
-        vec![static_dir.join(path).join("index.html")]
+        let dir = static_dir.join(path);
+        (vec![dir.join("index.html")], Some(dir))
     } else if Path::new(path).extension().is_none() {
-        vec![
-            static_dir.join(format!("{path}.html")),
-            static_dir.join(path).join("index.html"),
-        ]
+        let dir = static_dir.join(path);
+        (
+            vec![
+                static_dir.join(format!("{path}.html")),
+                dir.join("index.html"),
+            ],
+            Some(dir),
+        )
     } else {
-        vec![static_dir.join(path)]
+        (vec![static_dir.join(path)], None)
     };
 
     for candidate in candidates {
@@ -112,46 +140,399 @@ fn resolve_static_path(path: &str) -> Option<String> {
         }
     }
 
-    None
+    // No index file was found; fall back to the bare directory so `generate_response` can
+    // serve an autoindex listing for it when that mode is enabled.
+    dir_fallback
+        .filter(|dir| dir.exists() && dir.is_dir())
+        .map(|dir| dir.to_string_lossy().to_string())
+}
+
+/// A parsed `Range` header value, covering the three byte-range forms the
+/// HTTP spec allows: an open-ended range, a fully bounded range, and a
+/// suffix range measured back from the end of the resource.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRange {
+    From(u64),
+    Full(u64, u64),
+    Suffix(u64),
+}
+
+/// Parses a `Range` header value of the form `bytes=<spec>` into a `ByteRange`.
+///
+/// Supports `bytes=500-` (from), `bytes=500-999` (full), and `bytes=-500`
+/// (suffix). Multi-range requests (`bytes=0-1,2-3`) and malformed specs
+/// return `None`.
+pub fn parse_range_header(value: &str) -> Option<ByteRange> {
+    let spec = value.trim().strip_prefix("bytes=")?;
+    if spec.contains(',') {
+        return None;
+    }
+
+    let (start, end) = spec.split_once('-')?;
+    if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        Some(ByteRange::Suffix(suffix))
+    } else if end.is_empty() {
+        let start: u64 = start.parse().ok()?;
+        Some(ByteRange::From(start))
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end: u64 = end.parse().ok()?;
+        if end < start {
+            return None;
+        }
+        Some(ByteRange::Full(start, end))
+    }
+}
+
+/// Resolves a `ByteRange` against a resource's total length, returning the
+/// inclusive `(start, end)` byte offsets to serve.
+///
+/// Returns `None` if the range is unsatisfiable, i.e. it starts at or past
+/// the end of the resource.
+fn resolve_range(range: ByteRange, total: u64) -> Option<(u64, u64)> {
+    match range {
+        ByteRange::From(start) => (start < total).then_some((start, total.saturating_sub(1))),
+        ByteRange::Full(start, end) => {
+            (start < total).then_some((start, end.min(total.saturating_sub(1))))
+        }
+        ByteRange::Suffix(n) => {
+            if n == 0 || total == 0 {
+                None
+            } else {
+                let len = n.min(total);
+                Some((total - len, total - 1))
+            }
+        }
+    }
+}
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Converts a day count since the Unix epoch (1970-01-01) into a civil `(year, month, day)`.
+///
+/// Implements Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(z: i64) -> (i64, u32, u32) {
+    let z = z + 719468;
+    let era = if z >= 0 { z } else { z - 146096 } / 146097;
+    let doe = (z - era * 146097) as u64;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let y = yoe as i64 + era * 400;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let y = if m <= 2 { y + 1 } else { y };
+    (y, m, d)
+}
+
+/// The inverse of `civil_from_days`: converts a civil `(year, month, day)` into a day count
+/// since the Unix epoch.
+fn days_from_civil(y: i64, m: u32, d: u32) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = (y - era * 400) as u64;
+    let mp = ((m + 9) % 12) as u64;
+    let doy = (153 * mp + 2) / 5 + d as u64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe as i64 - 719468
+}
+
+/// Formats a Unix timestamp (seconds since epoch) as an RFC 1123 HTTP date,
+/// e.g. `Tue, 15 Nov 1994 08:12:31 GMT`.
+pub fn format_http_date(secs: u64) -> String {
+    let days = (secs / 86400) as i64;
+    let time_of_day = secs % 86400;
+    let weekday = WEEKDAYS[((days.rem_euclid(7) + 4) % 7) as usize];
+    let (year, month, day) = civil_from_days(days);
+    let hour = time_of_day / 3600;
+    let minute = (time_of_day % 3600) / 60;
+    let second = time_of_day % 60;
+
+    format!(
+        "{weekday}, {day:02} {} {year} {hour:02}:{minute:02}:{second:02} GMT",
+        MONTHS[(month - 1) as usize]
+    )
+}
+
+/// Parses an RFC 1123 HTTP date (e.g. `Tue, 15 Nov 1994 08:12:31 GMT`) into seconds since the
+/// Unix epoch. Returns `None` if `value` isn't a recognized HTTP date.
+pub fn parse_http_date(value: &str) -> Option<u64> {
+    let (_, rest) = value.trim().split_once(", ")?;
+    let mut parts = rest.split_whitespace();
+    let day: u32 = parts.next()?.parse().ok()?;
+    let month = parts.next()?;
+    let month = MONTHS.iter().position(|m| *m == month)? as u32 + 1;
+    let year: i64 = parts.next()?.parse().ok()?;
+    let mut time = parts.next()?.split(':');
+    let hour: u64 = time.next()?.parse().ok()?;
+    let minute: u64 = time.next()?.parse().ok()?;
+    let second: u64 = time.next()?.parse().ok()?;
+
+    let days = days_from_civil(year, month, day);
+    Some((days * 86400) as u64 + hour * 3600 + minute * 60 + second)
+}
+
+/// The body of a generated response.
+///
+/// Small or synthesized bodies (directory listings, error pages, empty bodies) are held fully
+/// in memory. A `File` body instead names a byte range on disk for `send_response` to stream
+/// directly to the socket, so serving large media never requires buffering the whole file.
+pub enum ResponseBody {
+    Bytes(Vec<u8>),
+    File { path: String, start: u64, len: u64 },
+}
+
+/// Escapes `&`, `<`, `>`, `"`, and `'` so untrusted text can be safely interpolated into HTML.
+fn html_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Builds an HTML directory listing for a resolved directory that has no index file.
+///
+/// Entries are read via `fs::read_dir` and rendered as `<a href>` links (names passed through
+/// `html_escape`, since filenames are attacker-controlled on any server accepting uploads),
+/// sorted with directories first and alphabetically thereafter, plus a link back to the parent
+/// directory.
+///
+/// `prefer_utf8` gets the same `; charset=utf-8` treatment as served files (see
+/// `mime_type_with_charset`), since this response is `text/html` too.
+fn generate_autoindex_response(dir_path: &str, prefer_utf8: bool) -> (String, String, ResponseBody) {
+    let mut entries: Vec<(String, bool)> = match fs::read_dir(dir_path) {
+        Ok(read_dir) => read_dir
+            .filter_map(|entry| entry.ok())
+            .map(|entry| {
+                let is_dir = entry.path().is_dir();
+                (entry.file_name().to_string_lossy().to_string(), is_dir)
+            })
+            .collect(),
+        Err(_) => return not_found_response(),
+    };
+
+    entries.sort_by(|(a_name, a_is_dir), (b_name, b_is_dir)| {
+        b_is_dir.cmp(a_is_dir).then_with(|| a_name.cmp(b_name))
+    });
+
+    let mut body = String::from("<html><body>\n<ul>\n<li><a href=\"../\">../</a></li>\n");
+    for (name, is_dir) in entries {
+        let suffix = if is_dir { "/" } else { "" };
+        let name = html_escape(&name);
+        body.push_str(&format!(
+            "<li><a href=\"{name}{suffix}\">{name}{suffix}</a></li>\n"
+        ));
+    }
+    body.push_str("</ul>\n</body></html>");
+
+    let body = body.into_bytes();
+    let content_type = if prefer_utf8 {
+        mime_type_with_charset("text/html")
+    } else {
+        "text/html".to_string()
+    };
+    let status_line = "HTTP/1.1 200 OK\r\n".to_string();
+    let headers = format!(
+        "Content-Length: {}\r\nContent-Type: {content_type}\r\n\r\n",
+        body.len()
+    );
+
+    (status_line, headers, ResponseBody::Bytes(body))
+}
+
+/// Builds the standard `404 Not Found` response.
+fn not_found_response() -> (String, String, ResponseBody) {
+    let body = b"<h1>404 Not Found</h1>".to_vec();
+    let status_line = "HTTP/1.1 404 Not Found\r\n".to_string();
+    let headers = format!(
+        "Content-Length: {}\r\nContent-Type: text/html\r\n\r\n",
+        body.len()
+    );
+
+    (status_line, headers, ResponseBody::Bytes(body))
 }
 
 /// Generates a complete HTTP response based on the contents of a file.
 ///
-/// If the file exists and can be read, it returns a `200 OK` response with the file contents.
-/// If the file cannot be read, it returns a `404 Not Found` response with a simple error message.
+/// If the file exists, it returns a `200 OK` response whose body streams the file from disk.
+/// If the file cannot be found, it returns a `404 Not Found` response with a simple error message.
+///
+/// When `range_header` carries a valid `Range` value, the matching byte range is served instead,
+/// as a `206 Partial Content` response with a `Content-Range` header, or a `416 Range Not
+/// Satisfiable` response if the range falls outside the file.
+///
+/// Every non-error response for a file also carries `ETag` and `Last-Modified` validators
+/// derived from the file's `fs::metadata`; directory listings carry neither. If `if_none_match` matches the computed `ETag`, or `if_modified_since`
+/// is at or after the file's modified time, the request short-circuits to a `304 Not Modified`
+/// response with no body.
+///
+/// When `accept_encoding` offers `gzip` or `deflate` and the file's MIME type is compressible
+/// (see `is_compressible`), the plain `200 OK` body is served compressed instead, with
+/// `Content-Encoding` and `Vary: Accept-Encoding` set and `Content-Length` recomputed. A
+/// precompressed `<file>.gz` sibling is served directly when present, to avoid recompressing
+/// on every request. Range responses are never compressed.
 ///
 /// # Arguments
 ///
 /// * `full_path` - A string slice representing the path to the file to be served.
+/// * `range_header` - The raw value of an incoming `Range` header, if any.
+/// * `if_none_match` - The raw value of an incoming `If-None-Match` header, if any.
+/// * `if_modified_since` - The raw value of an incoming `If-Modified-Since` header, if any.
+/// * `accept_encoding` - The raw value of an incoming `Accept-Encoding` header, if any.
+/// * `autoindex` - When `full_path` resolves to a directory with no index file, serve an HTML
+///   directory listing if `true`, or a `404 Not Found` if `false`.
+/// * `prefer_utf8` - When `true`, textual MIME types (see `mime_type_with_charset`) get
+///   `; charset=utf-8` appended to their `Content-Type`.
 ///
 /// # Returns
 ///
-/// A complete HTTP response as a `String`, including status line, headers, and body.
-pub fn generate_response(full_path: &str) -> (String, String, Vec<u8>) {
-    match fs::read(full_path) {
-        Ok(contents) => {
-            let mime_type = guess_mime_type(full_path);
+/// A complete HTTP response: status line, headers, and a `ResponseBody` for `send_response` to
+/// write out.
+pub fn generate_response(
+    full_path: &str,
+    range_header: Option<&str>,
+    if_none_match: Option<&str>,
+    if_modified_since: Option<&str>,
+    accept_encoding: Option<&str>,
+    autoindex: bool,
+    prefer_utf8: bool,
+) -> (String, String, ResponseBody) {
+    let metadata = match fs::metadata(full_path) {
+        Ok(metadata) => metadata,
+        Err(_) => return not_found_response(),
+    };
 
-            let status_line = "HTTP/1.1 200 OK\r\n".to_string();
-            let headers = format!(
-                "Content-Length: {}\r\nContent-Type: {}\r\n\r\n",
-                contents.len(),
-                mime_type
-            );
+    if metadata.is_dir() {
+        return if autoindex {
+            generate_autoindex_response(full_path, prefer_utf8)
+        } else {
+            not_found_response()
+        };
+    }
 
-            (status_line, headers, contents)
-        }
-        Err(_) => {
-            let body = b"<h1>404 Not Found</h1>".to_vec();
-            let status_line = "HTTP/1.1 404 Not Found\r\n".to_string();
-            let headers = format!(
-                "Content-Length: {}\r\nContent-Type: text/html\r\n\r\n",
-                body.len()
-            );
-
-            (status_line, headers, body)
+    let total = metadata.len();
+    let mtime_secs = metadata
+        .modified()
+        .ok()
+        .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let etag = format!("\"{total}-{mtime_secs}\"");
+    let last_modified = format_http_date(mtime_secs);
+    let mime_type = guess_mime_type(full_path);
+    let content_type = if prefer_utf8 {
+        mime_type_with_charset(mime_type)
+    } else {
+        mime_type.to_string()
+    };
+    // Compressible types must always advertise Vary, even on a response that isn't itself
+    // compressed, so a cache in front of this server doesn't serve that uncompressed body to a
+    // later client that does negotiate compression.
+    let vary_header = if is_compressible(mime_type) {
+        "Vary: Accept-Encoding\r\n"
+    } else {
+        ""
+    };
+
+    let not_modified = if_none_match.is_some_and(|value| value.trim() == etag)
+        || if_modified_since
+            .and_then(parse_http_date)
+            .is_some_and(|since| since >= mtime_secs);
+
+    if not_modified {
+        let status_line = "HTTP/1.1 304 Not Modified\r\n".to_string();
+        let headers =
+            format!("ETag: {etag}\r\nLast-Modified: {last_modified}\r\nContent-Length: 0\r\n\r\n");
+        return (status_line, headers, ResponseBody::Bytes(Vec::new()));
+    }
+
+    if let Some(range) = range_header.and_then(parse_range_header) {
+        return match resolve_range(range, total) {
+            Some((start, end)) => {
+                let len = end - start + 1;
+                let status_line = "HTTP/1.1 206 Partial Content\r\n".to_string();
+                let headers = format!(
+                    "Content-Range: bytes {start}-{end}/{total}\r\nContent-Length: {len}\r\nContent-Type: {content_type}\r\n{vary_header}Accept-Ranges: bytes\r\nETag: {etag}\r\nLast-Modified: {last_modified}\r\n\r\n",
+                );
+                (
+                    status_line,
+                    headers,
+                    ResponseBody::File {
+                        path: full_path.to_string(),
+                        start,
+                        len,
+                    },
+                )
+            }
+            None => {
+                let status_line = "HTTP/1.1 416 Range Not Satisfiable\r\n".to_string();
+                let headers = format!("Content-Range: bytes */{total}\r\nContent-Length: 0\r\n\r\n");
+                (status_line, headers, ResponseBody::Bytes(Vec::new()))
+            }
+        };
+    }
+
+    if is_compressible(mime_type) {
+        if let Some(encoding) = accept_encoding.and_then(preferred_encoding) {
+            let precompressed_path = format!("{full_path}.gz");
+            if encoding == "gzip" {
+                if let Ok(precompressed_meta) = fs::metadata(&precompressed_path) {
+                    let compressed_len = precompressed_meta.len();
+                    let status_line = "HTTP/1.1 200 OK\r\n".to_string();
+                    let headers = format!(
+                        "Content-Length: {compressed_len}\r\nContent-Type: {content_type}\r\nContent-Encoding: gzip\r\nVary: Accept-Encoding\r\nAccept-Ranges: bytes\r\nETag: {etag}\r\nLast-Modified: {last_modified}\r\n\r\n",
+                    );
+                    return (
+                        status_line,
+                        headers,
+                        ResponseBody::File {
+                            path: precompressed_path,
+                            start: 0,
+                            len: compressed_len,
+                        },
+                    );
+                }
+            }
+
+            if let Ok(contents) = fs::read(full_path) {
+                if let Ok(compressed) = compress(&contents, encoding) {
+                    let status_line = "HTTP/1.1 200 OK\r\n".to_string();
+                    let headers = format!(
+                        "Content-Length: {}\r\nContent-Type: {content_type}\r\nContent-Encoding: {encoding}\r\nVary: Accept-Encoding\r\nAccept-Ranges: bytes\r\nETag: {etag}\r\nLast-Modified: {last_modified}\r\n\r\n",
+                        compressed.len()
+                    );
+                    return (status_line, headers, ResponseBody::Bytes(compressed));
+                }
+            }
         }
     }
+
+    let status_line = "HTTP/1.1 200 OK\r\n".to_string();
+    let headers = format!(
+        "Content-Length: {total}\r\nContent-Type: {content_type}\r\n{vary_header}Accept-Ranges: bytes\r\nETag: {etag}\r\nLast-Modified: {last_modified}\r\n\r\n",
+    );
+
+    (
+        status_line,
+        headers,
+        ResponseBody::File {
+            path: full_path.to_string(),
+            start: 0,
+            len: total,
+        },
+    )
 }
 
 /// Guesses the MIME type of a file based on its extension.
@@ -206,20 +587,83 @@ fn guess_mime_type(path: &str) -> &'static str {
     }
 }
 
+/// Appends `; charset=utf-8` to textual MIME types, leaving binary types untouched.
+///
+/// Mirrors the `PREFER_UTF8` behavior browsers expect from static sites, so HTML, CSS, JS,
+/// JSON, plain text, and SVG responses aren't left for the client to guess the encoding of.
+fn mime_type_with_charset(mime_type: &str) -> String {
+    match mime_type {
+        "text/html" | "text/css" | "application/javascript" | "application/json"
+        | "text/plain" | "image/svg+xml" => format!("{mime_type}; charset=utf-8"),
+        _ => mime_type.to_string(),
+    }
+}
+
+/// Reports whether a MIME type benefits from on-the-fly compression.
+///
+/// Already-compressed media (raster images, video, audio, fonts, `application/octet-stream`) is left
+/// alone, since recompressing it wastes CPU for no size benefit.
+fn is_compressible(mime_type: &str) -> bool {
+    matches!(
+        mime_type,
+        "text/html"
+            | "text/css"
+            | "text/plain"
+            | "application/javascript"
+            | "application/json"
+            | "image/svg+xml"
+            | "application/wasm"
+    )
+}
+
+/// Picks the preferred `Content-Encoding` from an `Accept-Encoding` header value, preferring
+/// `gzip` over `deflate` when both are offered. Returns `None` if neither is offered.
+fn preferred_encoding(accept_encoding: &str) -> Option<&'static str> {
+    let offered: Vec<&str> = accept_encoding.split(',').map(str::trim).collect();
+    if offered.iter().any(|value| value.starts_with("gzip")) {
+        Some("gzip")
+    } else if offered.iter().any(|value| value.starts_with("deflate")) {
+        Some("deflate")
+    } else {
+        None
+    }
+}
+
+/// Compresses `contents` with the given encoding (`"gzip"` or `"deflate"`).
+fn compress(contents: &[u8], encoding: &str) -> std::io::Result<Vec<u8>> {
+    match encoding {
+        "gzip" => {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(contents)?;
+            encoder.finish()
+        }
+        "deflate" => {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(contents)?;
+            encoder.finish()
+        }
+        _ => Ok(contents.to_vec()),
+    }
+}
+
 /// Sends an HTTP-like response over the provided UnixStream socket asynchronously.
 ///
-/// The response is sent in three parts: status line, headers, and body.
-/// Each part is written sequentially to the socket. Errors are logged to stderr,
-/// and the function returns early if writing the status or headers fails.
+/// The response is sent in three parts: status line, headers, and body. Each part is written
+/// sequentially to the socket. Errors are logged to stderr, and the function returns early if
+/// writing the status or headers fails. A `ResponseBody::File` body is streamed from disk in
+/// fixed-size chunks rather than buffered, so memory use stays flat regardless of file size.
 ///
 /// # Arguments
 ///
 /// * `socket` - The UnixStream to send the response through.
 /// * `response_parts` - A tuple containing the status line (String),
-///   headers (String), and body (`Vec<u8>`).
+///   headers (String), and body (`ResponseBody`).
+/// * `skip_body` - When `true`, the status line and headers are sent but the body is omitted,
+///   as required for responses to `HEAD` requests.
 pub async fn send_response(
     socket: &mut UnixStream,
-    response_parts: (String, String, Vec<u8>),
+    response_parts: (String, String, ResponseBody),
+    skip_body: bool,
 ) {
     let (status, headers, body) = response_parts;
 
@@ -233,23 +677,391 @@ pub async fn send_response(
         return;
     }
 
-    if let Err(e) = socket.write_all(&body).await {
+    if skip_body {
+        return;
+    }
+
+    let result = match body {
+        ResponseBody::Bytes(bytes) => socket.write_all(&bytes).await,
+        ResponseBody::File { path, start, len } => stream_file(socket, &path, start, len).await,
+    };
+
+    if let Err(e) = result {
         eprintln!("Failed to write body: {}", e);
     }
 }
 
-/// Reads data from the provided UnixStream socket asynchronously.
+/// Streams `len` bytes starting at `start` from the file at `path` directly to `socket` in
+/// fixed-size chunks, keeping memory use flat regardless of file size.
+async fn stream_file(
+    socket: &mut UnixStream,
+    path: &str,
+    start: u64,
+    len: u64,
+) -> std::io::Result<()> {
+    let mut file = tokio::fs::File::open(path).await?;
+    file.seek(SeekFrom::Start(start)).await?;
+
+    let mut remaining = len;
+    let mut buf = [0u8; 64 * 1024];
+
+    while remaining > 0 {
+        let to_read = (buf.len() as u64).min(remaining) as usize;
+        let n = file.read(&mut buf[..to_read]).await?;
+        if n == 0 {
+            break;
+        }
+        socket.write_all(&buf[..n]).await?;
+        remaining -= n as u64;
+    }
+
+    Ok(())
+}
+
+/// Reads a single complete HTTP request from the provided UnixStream socket asynchronously.
 ///
-/// Returns a tuple containing the request as a String and the socket itself.
+/// Loops on the socket, accumulating bytes until the `\r\n\r\n` header terminator has been
+/// seen, then reads up to `Content-Length` more bytes as the body. This replaces a fixed
+/// 1024-byte read, which silently truncated requests with large header blocks or bodies.
+///
+/// `leftover` is a per-connection buffer owned by the caller. A pipelined client can write
+/// several requests in one `send`, so a single `socket.read` may return bytes belonging to the
+/// *next* request past the end of this one's body; those bytes are left in `leftover` instead
+/// of being discarded, and `read_socket` consumes `leftover` first on every call before reading
+/// more from the socket. Callers should pass the same `Vec` (starting empty) across the
+/// lifetime of a connection.
 ///
 /// # Errors
-/// Returns an error if reading from the socket fails.
+/// Returns an error if reading from the socket fails, or if the connection closes before the
+/// headers are complete.
 pub async fn read_socket(
     socket: &mut UnixStream,
-) -> Result<String, std::io::Error> {
-    let mut buf = [0; 1024];
-    let n = socket.read(&mut buf).await?;
-    Ok(String::from_utf8_lossy(&buf[..n]).to_string())
+    leftover: &mut Vec<u8>,
+) -> Result<Request, std::io::Error> {
+    let mut buf = std::mem::take(leftover);
+    let mut chunk = [0u8; 4096];
+
+    let header_end = loop {
+        if let Some(pos) = find_header_terminator(&buf) {
+            break pos;
+        }
+        let n = socket.read(&mut chunk).await?;
+        if n == 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::UnexpectedEof,
+                "connection closed before headers were complete",
+            ));
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    };
+
+    let head = String::from_utf8_lossy(&buf[..header_end]).to_string();
+    let mut after_headers = buf.split_off(header_end + 4);
+
+    let mut lines = head.lines();
+    let request_line = lines.next().unwrap_or("");
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("").to_string();
+    let path = parts.next().unwrap_or("/").to_string();
+    let version = parts.next().unwrap_or("").to_string();
+
+    let mut headers = HashMap::new();
+    for line in lines {
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let content_length: usize = headers
+        .get("content-length")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+
+    while after_headers.len() < content_length {
+        let n = socket.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        after_headers.extend_from_slice(&chunk[..n]);
+    }
+
+    // Anything past the declared body belongs to a subsequent pipelined request; keep it
+    // around for the next `read_socket` call instead of discarding it.
+    *leftover = after_headers.split_off(content_length.min(after_headers.len()));
+    let body = after_headers;
+
+    Ok(Request {
+        method,
+        path,
+        version,
+        headers,
+        body,
+    })
+}
+
+/// Finds the index of the start of the `\r\n\r\n` header terminator in `buf`, if present.
+fn find_header_terminator(buf: &[u8]) -> Option<usize> {
+    buf.windows(4).position(|window| window == b"\r\n\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two GET requests pipelined into a single write must be read back as two distinct
+    /// requests, with no bytes from the second request swallowed into the first one's body.
+    #[tokio::test]
+    async fn read_socket_splits_pipelined_requests() {
+        let dir = std::env::temp_dir();
+        let socket_path = dir.join(format!(
+            "ronfire-test-{}-{:?}.sock",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        let _ = std::fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path).unwrap();
+
+        let client = tokio::spawn({
+            let socket_path = socket_path.clone();
+            async move {
+                let mut stream = UnixStream::connect(&socket_path).await.unwrap();
+                stream
+                    .write_all(
+                        b"GET /index.html HTTP/1.1\r\n\r\nGET /style.css HTTP/1.1\r\n\r\n",
+                    )
+                    .await
+                    .unwrap();
+                stream
+            }
+        });
+
+        let (mut server_socket, _) = listener.accept().await.unwrap();
+        let mut leftover = Vec::new();
+
+        let first = read_socket(&mut server_socket, &mut leftover).await.unwrap();
+        assert_eq!(first.path, "/index.html");
+        assert!(first.body.is_empty());
+
+        let second = read_socket(&mut server_socket, &mut leftover).await.unwrap();
+        assert_eq!(second.path, "/style.css");
+        assert!(second.body.is_empty());
+
+        client.await.unwrap();
+        let _ = std::fs::remove_file(&socket_path);
+    }
+
+    #[test]
+    fn parse_range_header_parses_all_three_forms() {
+        assert_eq!(parse_range_header("bytes=500-"), Some(ByteRange::From(500)));
+        assert_eq!(
+            parse_range_header("bytes=500-999"),
+            Some(ByteRange::Full(500, 999))
+        );
+        assert_eq!(parse_range_header("bytes=-500"), Some(ByteRange::Suffix(500)));
+    }
+
+    #[test]
+    fn parse_range_header_rejects_malformed_and_multi_range() {
+        assert_eq!(parse_range_header("bytes=0-1,2-3"), None);
+        assert_eq!(parse_range_header("bytes=999-500"), None);
+        assert_eq!(parse_range_header("bytes=abc-"), None);
+        assert_eq!(parse_range_header("0-500"), None);
+    }
+
+    #[test]
+    fn resolve_range_from_extends_to_end_of_resource() {
+        assert_eq!(resolve_range(ByteRange::From(10), 100), Some((10, 99)));
+    }
+
+    #[test]
+    fn resolve_range_full_clamps_end_to_resource_length() {
+        assert_eq!(resolve_range(ByteRange::Full(10, 1_000), 100), Some((10, 99)));
+        assert_eq!(resolve_range(ByteRange::Full(10, 50), 100), Some((10, 50)));
+    }
+
+    #[test]
+    fn resolve_range_suffix_measures_from_the_end() {
+        assert_eq!(resolve_range(ByteRange::Suffix(10), 100), Some((90, 99)));
+        // A suffix larger than the resource is clamped to the whole thing.
+        assert_eq!(resolve_range(ByteRange::Suffix(1_000), 100), Some((0, 99)));
+    }
+
+    #[test]
+    fn resolve_range_rejects_start_at_or_past_total() {
+        assert_eq!(resolve_range(ByteRange::From(100), 100), None);
+        assert_eq!(resolve_range(ByteRange::Full(100, 200), 100), None);
+        assert_eq!(resolve_range(ByteRange::Suffix(0), 100), None);
+        assert_eq!(resolve_range(ByteRange::Suffix(10), 0), None);
+    }
+
+    #[test]
+    fn http_date_round_trips_through_format_and_parse() {
+        // 1994-11-15T08:12:31Z, the canonical RFC 1123 example.
+        let secs = 784887151;
+        let formatted = format_http_date(secs);
+        assert_eq!(formatted, "Tue, 15 Nov 1994 08:12:31 GMT");
+        assert_eq!(parse_http_date(&formatted), Some(secs));
+    }
+
+    #[test]
+    fn parse_http_date_rejects_garbage() {
+        assert_eq!(parse_http_date("not a date"), None);
+        assert_eq!(parse_http_date(""), None);
+    }
+
+    fn write_temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "ronfire-test-{}-{:?}-{name}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn generate_response_short_circuits_to_304_on_matching_etag() {
+        let path = write_temp_file("etag.txt", b"hello world");
+        let full_path = path.to_string_lossy().to_string();
+
+        let (status, headers, _) = generate_response(&full_path, None, None, None, None, false, false);
+        let etag = headers
+            .lines()
+            .find_map(|line| line.strip_prefix("ETag: "))
+            .unwrap()
+            .to_string();
+        assert!(status.starts_with("HTTP/1.1 200"));
+
+        let (status, headers, body) =
+            generate_response(&full_path, None, Some(&etag), None, None, false, false);
+        assert!(status.starts_with("HTTP/1.1 304"));
+        assert!(headers.contains("Content-Length: 0"));
+        assert!(matches!(body, ResponseBody::Bytes(bytes) if bytes.is_empty()));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn generate_response_short_circuits_to_304_when_not_modified_since() {
+        let path = write_temp_file("since.txt", b"hello world");
+        let full_path = path.to_string_lossy().to_string();
+
+        let mtime_secs = std::fs::metadata(&path)
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let since = format_http_date(mtime_secs);
+
+        let (status, _, _) =
+            generate_response(&full_path, None, None, Some(&since), None, false, false);
+        assert!(status.starts_with("HTTP/1.1 304"));
+
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn generate_autoindex_response_lists_dirs_first_then_alpha_and_escapes_names() {
+        let dir = std::env::temp_dir().join(format!(
+            "ronfire-test-autoindex-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("b.txt"), b"").unwrap();
+        std::fs::write(dir.join("a.txt"), b"").unwrap();
+        std::fs::create_dir(dir.join("zsub")).unwrap();
+        std::fs::write(dir.join("\"><script>alert(1)"), b"").unwrap();
+
+        let (status, headers, body) =
+            generate_autoindex_response(&dir.to_string_lossy(), false);
+        assert!(status.starts_with("HTTP/1.1 200"));
+        assert!(headers.contains("Content-Type: text/html\r\n"));
+        assert!(!headers.contains("charset"));
+
+        let ResponseBody::Bytes(bytes) = body else {
+            panic!("expected an in-memory body");
+        };
+        let html = String::from_utf8(bytes).unwrap();
+
+        // Directories sort before files, and each group is alphabetical.
+        let zsub_pos = html.find("zsub/").unwrap();
+        let a_pos = html.find("a.txt").unwrap();
+        let b_pos = html.find("b.txt").unwrap();
+        assert!(zsub_pos < a_pos);
+        assert!(a_pos < b_pos);
+
+        // The malicious filename is escaped, not interpolated raw.
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("&lt;script&gt;"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn generate_autoindex_response_applies_charset_when_prefer_utf8() {
+        let dir = std::env::temp_dir().join(format!(
+            "ronfire-test-autoindex-utf8-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir(&dir).unwrap();
+
+        let (_, headers, _) = generate_autoindex_response(&dir.to_string_lossy(), true);
+        assert!(headers.contains("Content-Type: text/html; charset=utf-8\r\n"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn is_compressible_covers_textual_types_and_excludes_binary() {
+        assert!(is_compressible("text/html"));
+        assert!(is_compressible("application/javascript"));
+        assert!(is_compressible("application/wasm"));
+        assert!(!is_compressible("image/png"));
+        assert!(!is_compressible("application/octet-stream"));
+    }
+
+    #[test]
+    fn preferred_encoding_prefers_gzip_over_deflate() {
+        assert_eq!(preferred_encoding("gzip, deflate"), Some("gzip"));
+        assert_eq!(preferred_encoding("deflate, gzip"), Some("gzip"));
+        assert_eq!(preferred_encoding("deflate"), Some("deflate"));
+        assert_eq!(preferred_encoding("br"), None);
+        assert_eq!(preferred_encoding(""), None);
+    }
+
+    #[test]
+    fn compress_round_trips_through_gzip_and_deflate() {
+        let contents = b"hello world, hello world, hello world";
+
+        let gzipped = compress(contents, "gzip").unwrap();
+        let mut decoder = flate2::read::GzDecoder::new(&gzipped[..]);
+        let mut decompressed = Vec::new();
+        std::io::Read::read_to_end(&mut decoder, &mut decompressed).unwrap();
+        assert_eq!(decompressed, contents);
+
+        let deflated = compress(contents, "deflate").unwrap();
+        let mut decoder = flate2::read::DeflateDecoder::new(&deflated[..]);
+        let mut decompressed = Vec::new();
+        std::io::Read::read_to_end(&mut decoder, &mut decompressed).unwrap();
+        assert_eq!(decompressed, contents);
+    }
+
+    #[test]
+    fn generate_response_applies_vary_header_even_when_not_negotiated() {
+        let path = write_temp_file("vary.html", b"<html></html>");
+        let full_path = path.to_string_lossy().to_string();
+
+        let (_, headers, _) = generate_response(&full_path, None, None, None, None, false, false);
+        assert!(headers.contains("Vary: Accept-Encoding\r\n"));
+
+        let _ = std::fs::remove_file(&path);
+    }
 }
 
 
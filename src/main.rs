@@ -1,6 +1,4 @@
-use ronfire::{
-    create_socket, generate_response, parse_request, read_socket, send_response,
-};
+use ronfire::{create_socket, generate_response, parse_request, read_socket, send_response};
 use std::env;
 
 #[tokio::main]
@@ -11,21 +9,41 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     let listener = create_socket(socket_path).expect("Could not create socket");
 
+    // Opt-in HTML directory listings for directories with no index file.
+    let autoindex = env::var("RONFIRE_AUTOINDEX").is_ok();
+    // Opt-in `; charset=utf-8` on textual Content-Type headers.
+    let prefer_utf8 = env::var("RONFIRE_PREFER_UTF8").is_ok();
+
     loop {
         let (mut socket, _) = listener.accept().await?;
 
         tokio::spawn(async move {
+            let mut leftover = Vec::new();
+
             loop {
-                match read_socket(&mut socket).await {
+                match read_socket(&mut socket, &mut leftover).await {
                     Ok(request) => {
-                        // Check for keep-alive
-                        let keep_alive = request
-                            .contains("Connection: keep-alive")
-                            || (request.contains("HTTP/1.1")
-                                && !request.contains("Connection: close"));
+                        // HTTP/1.1 defaults to persistent connections; HTTP/1.0 defaults to
+                        // closing after one response unless the client opts in explicitly.
+                        let keep_alive = match request.header("connection") {
+                            Some(value) => !value.eq_ignore_ascii_case("close"),
+                            None => request.version == "HTTP/1.1",
+                        };
 
                         if let Some(full_path) = parse_request(&request) {
-                            let mut response = generate_response(&full_path);
+                            let range_header = request.header("range");
+                            let if_none_match = request.header("if-none-match");
+                            let if_modified_since = request.header("if-modified-since");
+                            let accept_encoding = request.header("accept-encoding");
+                            let mut response = generate_response(
+                                &full_path,
+                                range_header,
+                                if_none_match,
+                                if_modified_since,
+                                accept_encoding,
+                                autoindex,
+                                prefer_utf8,
+                            );
 
                             // Append appropriate Connection header
                             let connection_header = if keep_alive {
@@ -38,9 +56,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                             response.1 =
                                 format!("{}{}", connection_header, response.1);
 
-                            send_response(&mut socket, response).await;
+                            let is_head = request.method == "HEAD";
+                            send_response(&mut socket, response, is_head).await;
                         } else {
-                            eprintln!("Invalid request: {}", request);
+                            eprintln!(
+                                "Invalid request: {} {}",
+                                request.method, request.path
+                            );
                             break;
                         }
 